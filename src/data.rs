@@ -32,6 +32,8 @@ pub enum DataError {
     UnexpectedDataSource,
 }
 
+const REDACTION_PLACEHOLDER: &[u8] = b"[REDACTED]";
+
 pub struct DataDecoder<R: Read>(pub R);
 
 impl<R: Read> DataDecoder<R> {
@@ -64,22 +66,53 @@ impl<R: Read> DataDecoder<R> {
     }
 }
 
+/// Writes the raw client/origin streams out to separate writers. This path has no PTY
+/// metadata to check echo state against, so when `redact_client` is set every
+/// `DataSource::Client` chunk is treated as potentially sensitive (e.g. a password piped
+/// to stdin of a non-PTY command) and replaced with a placeholder rather than written
+/// verbatim.
 pub fn generate_data_file<R: Read, W: Write>(
     mut decoder: DataDecoder<R>,
     mut client_data_writer: W,
     mut origin_data_writer: W,
+    redact_client: bool,
 ) -> Result<(), DataError> {
+    let mut redacted_bytes = 0usize;
+
     loop {
         let data_packet = match decoder.next()? {
             Some(packet) => packet,
-            None => return Ok(()),
+            None => break,
         };
-        let writer = match data_packet.source {
-            DataSource::Client => &mut client_data_writer,
-            DataSource::Origin => &mut origin_data_writer,
-        };
-        writer
-            .write(&data_packet.data)
-            .map_err(DataError::WriteError)?;
+
+        match data_packet.source {
+            DataSource::Client if redact_client => {
+                redacted_bytes += data_packet.data.len();
+                client_data_writer
+                    .write(REDACTION_PLACEHOLDER)
+                    .map_err(DataError::WriteError)?;
+            }
+            DataSource::Client => {
+                client_data_writer
+                    .write(&data_packet.data)
+                    .map_err(DataError::WriteError)?;
+            }
+            DataSource::Origin => {
+                origin_data_writer
+                    .write(&data_packet.data)
+                    .map_err(DataError::WriteError)?;
+            }
+        }
+    }
+
+    if redact_client {
+        write!(
+            client_data_writer,
+            "\n[redaction: {} bytes of client input withheld, no PTY metadata was available to check terminal echo state]\n",
+            redacted_bytes
+        )
+        .map_err(DataError::WriteError)?;
     }
+
+    Ok(())
 }
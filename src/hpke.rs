@@ -23,6 +23,7 @@ type Aead = ChaCha20Poly1305;
 type Kdf = HkdfSha256;
 
 type PrivateKey = <Kem as KemTrait>::PrivateKey;
+type PublicKey = <Kem as KemTrait>::PublicKey;
 type EncappedKey = <Kem as KemTrait>::EncappedKey;
 
 #[derive(Error, Debug)]
@@ -41,6 +42,10 @@ pub enum HPKEDecryptionError {
     ContextCreation(HpkeError),
     #[error("error decrypting buffer")]
     Decrypt(HpkeError),
+    #[error("invalid base64 sender public key")]
+    SenderKeyInvalidB64(DecodeError),
+    #[error("invalid sender public key")]
+    SenderKeyInvalid(HpkeError),
 }
 
 #[derive(Serialize, Deserialize)]
@@ -82,13 +87,23 @@ impl<R: Read> Ctx<R> {
         let encapped_key = EncappedKey::from_bytes(&encapped_key)
             .map_err(HPKEDecryptionError::EncappedKeyInvalid)?;
 
-        let inner = hpke::setup_receiver::<Aead, Kdf, Kem>(
-            &OpModeR::Base,
-            &private_key,
-            &encapped_key,
-            &[],
-        )
-        .map_err(HPKEDecryptionError::ContextCreation)?;
+        let sender_public_key = meta
+            .sender_public_key
+            .as_ref()
+            .map(|sender_key| -> Result<PublicKey, HPKEDecryptionError> {
+                let sender_key =
+                    base64::decode(sender_key).map_err(HPKEDecryptionError::SenderKeyInvalidB64)?;
+                PublicKey::from_bytes(&sender_key).map_err(HPKEDecryptionError::SenderKeyInvalid)
+            })
+            .transpose()?;
+
+        let mode = match &sender_public_key {
+            Some(sender_public_key) => OpModeR::Auth(sender_public_key),
+            None => OpModeR::Base,
+        };
+
+        let inner = hpke::setup_receiver::<Aead, Kdf, Kem>(&mode, &private_key, &encapped_key, &[])
+            .map_err(HPKEDecryptionError::ContextCreation)?;
 
         Ok(Ctx {
             inner,
@@ -0,0 +1,9 @@
+#![warn(rust_2018_idioms)]
+pub mod data;
+pub mod hpke;
+pub mod metadata;
+pub mod pty;
+pub mod search;
+mod session;
+
+pub use session::{PendingSession, SessionError, SessionReader};
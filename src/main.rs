@@ -1,13 +1,13 @@
 #![warn(rust_2018_idioms)]
-mod data;
-mod hpke;
-mod metadata;
-mod pty;
+mod replay;
 mod zip;
 
-use crate::metadata::Metadata;
-use clap::Parser;
-use data::{generate_data_file, DataDecoder};
+use clap::{ArgEnum, Parser};
+use ssh_log_cli::data::{generate_data_file, DataDecoder, DataSource};
+use ssh_log_cli::metadata::Metadata;
+use ssh_log_cli::search::{search_session, Pattern};
+use ssh_log_cli::{hpke, pty};
+use regex::Regex;
 use std::fs::File;
 use std::io::{Read, Write};
 use std::path::Path;
@@ -19,6 +19,13 @@ const CLIENT_DATA_FILE_NAME: &str = "data_from_client.txt";
 const SERVER_DATA_FILE_NAME: &str = "data_from_server.txt";
 const REPLAY_DATA_FILE_NAME: &str = "term_data.txt";
 const REPLAY_TIMES_FILE_NAME: &str = "term_times.txt";
+const ASCIICAST_FILE_NAME: &str = "session.cast";
+
+#[derive(ArgEnum, Clone)]
+enum OutputFormat {
+    Scriptreplay,
+    Asciicast,
+}
 
 #[derive(Parser)]
 #[clap(about, author, version)]
@@ -57,6 +64,45 @@ struct DecryptOptions {
         help = "Output ZIP file name for the decrypted session data"
     )]
     output_file_name: Option<String>,
+
+    #[clap(
+        long,
+        help = "File containing the base64 encoded public key of the expected sender, decryption fails if the session was not authenticated with this key"
+    )]
+    expect_sender: Option<String>,
+
+    #[clap(
+        long,
+        arg_enum,
+        default_value = "scriptreplay",
+        help = "Output format used for the replay data when the session has a PTY"
+    )]
+    format: OutputFormat,
+
+    #[clap(
+        long,
+        default_value_t = 1.0,
+        help = "Scale all replay delays by this factor, e.g. 2.0 replays twice as fast"
+    )]
+    speed: f32,
+
+    #[clap(
+        long,
+        help = "Clamp any single inter-packet delay to at most this many seconds"
+    )]
+    idle_limit: Option<f32>,
+
+    #[clap(
+        long,
+        help = "Replay instantly, without waiting between packets"
+    )]
+    instant: bool,
+
+    #[clap(
+        long,
+        help = "Disable redaction of client input that may contain secrets (terminal echo disabled, e.g. password prompts; or no PTY was allocated at all, e.g. a password piped to stdin)"
+    )]
+    no_redact: bool,
 }
 
 #[derive(Parser)]
@@ -65,10 +111,40 @@ struct GenerateKeyPairOptions {
     output_file_name: String,
 }
 
+#[derive(Parser)]
+struct SearchOptions {
+    #[clap(
+        short = 'd',
+        long,
+        help = "Directory containing encrypted session files"
+    )]
+    directory: String,
+
+    #[clap(
+        short = 'k',
+        long,
+        help = "File containing the base64 encoded private key"
+    )]
+    private_key_filename: String,
+
+    #[clap(help = "Literal string (or regex with --regex) to search for")]
+    pattern: String,
+
+    #[clap(long, help = "Treat the pattern as a regular expression")]
+    regex: bool,
+
+    #[clap(
+        long,
+        help = "Disable redaction of client input recorded while terminal echo was disabled (e.g. password prompts) or in sessions with no PTY"
+    )]
+    no_redact: bool,
+}
+
 #[derive(Parser)]
 enum Command {
     Decrypt(DecryptOptions),
     GenerateKeyPair(GenerateKeyPairOptions),
+    Search(SearchOptions),
 }
 
 fn run_pty_decode<R: Read>(
@@ -93,24 +169,45 @@ fn run_pty_decode<R: Read>(
     ))
 }
 
-fn run_raw_decode<R: Read>(decoder: DataDecoder<R>, base_path: &Path) -> Result<(), String> {
+fn run_asciicast_decode<R: Read>(
+    metadata: &Metadata,
+    decoder: DataDecoder<R>,
+    base_path: &Path,
+    redact_client: bool,
+) -> Result<(), String> {
+    let asciicast_fname = base_path.join(ASCIICAST_FILE_NAME);
+    let asciicast_fp =
+        File::create(&asciicast_fname).map_err(|_| "Could not create output asciicast file")?;
+
+    pty::generate_asciicast(metadata, decoder, asciicast_fp, redact_client)
+        .map_err(|_| "Could not parse pty data")
+}
+
+fn run_raw_decode<R: Read>(
+    decoder: DataDecoder<R>,
+    base_path: &Path,
+    redact_client: bool,
+) -> Result<(), String> {
     let client_data_fp = File::create(base_path.join(CLIENT_DATA_FILE_NAME))
         .map_err(|_| "Could not create client data file")?;
     let server_data_fp = File::create(base_path.join(SERVER_DATA_FILE_NAME))
         .map_err(|_| "Could not create server data file")?;
 
-    generate_data_file(decoder, client_data_fp, server_data_fp)
+    generate_data_file(decoder, client_data_fp, server_data_fp, redact_client)
         .map_err(|_| "Could parse write raw data".into())
 }
 
-fn replay_pty_session(data_fname: &str, times_fname: &str) -> Result<(), String> {
-    std::process::Command::new("scriptreplay")
-        .args(["--timing", times_fname, data_fname])
-        .spawn()
-        .map_err(|_| "Could not launch scriptreplay, make sure you have it in your PATH.")?
-        .wait()
-        .map_err(|_| "scriptreplay error")?;
-    Ok(())
+fn replay_pty_session(
+    data_fname: &str,
+    times_fname: &str,
+    opts: &DecryptOptions,
+) -> Result<(), String> {
+    let replay_opts = replay::ReplayOptions {
+        speed: opts.speed,
+        idle_limit: opts.idle_limit,
+        instant: opts.instant,
+    };
+    replay::replay(data_fname, times_fname, &replay_opts).map_err(|_| "Could not replay session".into())
 }
 
 fn create_zip_output(fname: &str, path: &Path) -> Result<(), String> {
@@ -128,27 +225,50 @@ fn create_zip_output(fname: &str, path: &Path) -> Result<(), String> {
 }
 
 fn run_decrypt(opts: DecryptOptions) -> Result<(), String> {
-    let mut input_file =
-        File::open(&opts.input_filename).map_err(|_| "Could not open input file")?;
+    let input_file = File::open(&opts.input_filename).map_err(|_| "Could not open input file")?;
     let private_key_base64 = fs::read_to_string(&opts.private_key_filename)
         .map_err(|_| "Failed to read private key from file")?;
 
-    let metadata = Metadata::read(&mut input_file).map_err(|_| "Could not read metadata")?;
-    let reader = hpke::Ctx::new(&metadata, private_key_base64, input_file)
+    let pending =
+        ssh_log_cli::PendingSession::read(input_file).map_err(|_| "Could not read metadata")?;
+
+    if let Some(expect_sender_filename) = &opts.expect_sender {
+        let expected_sender = fs::read_to_string(expect_sender_filename)
+            .map_err(|_| "Could not read expected sender key from file")?;
+        if pending.metadata.sender_public_key.as_deref().map(str::trim)
+            != Some(expected_sender.trim())
+        {
+            return Err("Session sender key does not match expected sender".into());
+        }
+    }
+
+    let session = pending
+        .open(private_key_base64)
         .map_err(|_| "Could not create decryption context")?;
-    let decoder = DataDecoder(reader);
+    let metadata = session.metadata.clone();
+    let decoder = session.into_decoder();
 
     let temp_dir = tempdir().map_err(|_| "Could not create temporary directory")?;
     let base_path = temp_dir.path();
     match metadata.pty {
-        Some(_) => {
-            let (data_fname, times_fname) = run_pty_decode(&metadata, decoder, base_path)?;
-            if opts.replay {
-                return replay_pty_session(&data_fname, &times_fname);
+        Some(_) => match opts.format {
+            OutputFormat::Scriptreplay => {
+                let (data_fname, times_fname) = run_pty_decode(&metadata, decoder, base_path)?;
+                if opts.replay {
+                    return replay_pty_session(&data_fname, &times_fname, &opts);
+                }
             }
-        }
+            OutputFormat::Asciicast => {
+                if opts.replay {
+                    return Err(
+                        "Replay is not supported with asciicast output, play the exported .cast file with an asciinema player".into(),
+                    );
+                }
+                run_asciicast_decode(&metadata, decoder, base_path, !opts.no_redact)?;
+            }
+        },
         None => {
-            run_raw_decode(decoder, base_path)?;
+            run_raw_decode(decoder, base_path, !opts.no_redact)?;
         }
     }
 
@@ -162,6 +282,63 @@ fn run_decrypt(opts: DecryptOptions) -> Result<(), String> {
     create_zip_output(&out_file_name, &base_path)
 }
 
+fn run_search(opts: SearchOptions) -> Result<(), String> {
+    let private_key_base64 = fs::read_to_string(&opts.private_key_filename)
+        .map_err(|_| "Failed to read private key from file")?;
+
+    let pattern = if opts.regex {
+        Regex::new(&opts.pattern)
+            .map(Pattern::Regex)
+            .map_err(|_| "Invalid regex pattern")?
+    } else {
+        Pattern::Literal(opts.pattern)
+    };
+
+    let entries = fs::read_dir(&opts.directory).map_err(|_| "Could not read session directory")?;
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(_) => continue,
+        };
+        if !entry.path().is_file() {
+            continue;
+        }
+
+        let file = match File::open(entry.path()) {
+            Ok(file) => file,
+            Err(_) => continue,
+        };
+
+        let matches = match search_session(file, private_key_base64.clone(), &pattern, !opts.no_redact)
+        {
+            Ok(matches) => matches,
+            Err(_) => {
+                eprintln!(
+                    "Skipping {}: could not be decrypted with this key",
+                    entry.path().display()
+                );
+                continue;
+            }
+        };
+
+        for data_match in matches {
+            let source = match data_match.source {
+                DataSource::Origin => "origin",
+                DataSource::Client => "client",
+            };
+            println!(
+                "{}\t{}\t{:.6}\t{}",
+                entry.path().display(),
+                source,
+                data_match.elapsed.as_secs_f64(),
+                data_match.line
+            );
+        }
+    }
+
+    Ok(())
+}
+
 fn run_generate_key_pair(opts: GenerateKeyPairOptions) -> Result<(), String> {
     let public_fname = format!("{}.pub", opts.output_file_name);
     let mut private_fp =
@@ -183,6 +360,7 @@ fn run(options: Options) -> Result<(), String> {
     match options.command {
         Command::GenerateKeyPair(opts) => run_generate_key_pair(opts),
         Command::Decrypt(opts) => run_decrypt(opts),
+        Command::Search(opts) => run_search(opts),
     }
 }
 
@@ -29,6 +29,10 @@ pub struct Metadata {
     pub encapsulated_key: String,
     pub pty: Option<PTYMetadata>,
     pub exit_data: Option<ExitData>,
+    /// Base64 encoded long-term static public key of the sender, present when the
+    /// session was encrypted in HPKE authenticated mode.
+    #[serde(default)]
+    pub sender_public_key: Option<String>,
 }
 
 #[derive(Error, Debug)]
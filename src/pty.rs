@@ -4,14 +4,26 @@ use std::{
 };
 
 use chrono::{DateTime, Utc};
+use serde::Serialize;
 use thiserror::Error;
 
 use crate::{
     data::{DataDecoder, DataError, DataSource},
-    metadata::{ExitData, Metadata},
+    metadata::{ExitData, Metadata, PTYMetadata},
 };
 
 const PTY_MODE_ECHO: &str = "ECHO";
+const ASCIICAST_VERSION: u8 = 2;
+const REDACTION_PLACEHOLDER: &str = "[REDACTED]";
+
+/// Whether the client's keystrokes were echoed back by the PTY. When this is false,
+/// client input may include secrets (e.g. a password typed at a prompt) and must not
+/// be persisted verbatim.
+pub(crate) fn echo_enabled(pty: &PTYMetadata) -> bool {
+    pty.modes
+        .iter()
+        .any(|(mode, value)| mode == PTY_MODE_ECHO && *value != 0)
+}
 
 #[derive(Error, Debug)]
 pub enum PTYParserError {
@@ -21,6 +33,23 @@ pub enum PTYParserError {
     WriteError(std::io::Error),
     #[error("ssh session has no PTY allocated")]
     PTYNotFound,
+    #[error("could not encode asciicast event")]
+    Json(serde_json::Error),
+}
+
+#[derive(Serialize)]
+struct AsciicastHeader {
+    version: u8,
+    width: u32,
+    height: u32,
+    timestamp: u64,
+    env: AsciicastEnv,
+}
+
+#[derive(Serialize)]
+struct AsciicastEnv {
+    #[serde(rename = "TERM")]
+    term: String,
 }
 
 pub fn generate_replay<R: Read, W: Write>(
@@ -34,11 +63,6 @@ pub fn generate_replay<R: Read, W: Write>(
         None => return Err(PTYParserError::PTYNotFound),
     };
 
-    let echo_enabled = pty
-        .modes
-        .iter()
-        .any(|(mode, value)| mode == PTY_MODE_ECHO && *value != 0);
-
     write!(
         data_writer,
         "Session started on {} [TERM=\"{}\" COLUMNS=\"{}\" LINES=\"{}\"]\n",
@@ -99,6 +123,70 @@ fn write_exit_data<W: Write>(mut writer: W, data: &Option<ExitData>) -> Result<(
     Ok(())
 }
 
+/// Writes the session as an asciicast v2 stream (newline-delimited JSON), playable in
+/// any asciinema player without a local `scriptreplay` binary. When `redact_client` is
+/// set, client keystrokes recorded while the PTY had echo disabled (e.g. a password
+/// typed at a prompt) are replaced with a placeholder rather than written verbatim.
+pub fn generate_asciicast<R: Read, W: Write>(
+    meta: &Metadata,
+    mut decoder: DataDecoder<R>,
+    mut writer: W,
+    redact_client: bool,
+) -> Result<(), PTYParserError> {
+    let pty = match &meta.pty {
+        Some(pty_meta) => pty_meta,
+        None => return Err(PTYParserError::PTYNotFound),
+    };
+    let echo_enabled = echo_enabled(pty);
+
+    let header = AsciicastHeader {
+        version: ASCIICAST_VERSION,
+        width: pty.width,
+        height: pty.height,
+        timestamp: meta.started_at,
+        env: AsciicastEnv {
+            term: pty.term.clone().unwrap_or("unknown".to_string()),
+        },
+    };
+    write_asciicast_line(&mut writer, &header)?;
+
+    loop {
+        let data_packet = match decoder.next().map_err(PTYParserError::ReadError)? {
+            Some(packet) => packet,
+            None => return Ok(()),
+        };
+
+        let event_code = match data_packet.source {
+            DataSource::Origin => "o",
+            DataSource::Client => "i",
+        };
+        let elapsed_secs = data_packet.elapsed.as_secs_f64();
+
+        if redact_client && matches!(data_packet.source, DataSource::Client) && !echo_enabled {
+            let placeholder = format!(
+                "{} ({} bytes)",
+                REDACTION_PLACEHOLDER,
+                data_packet.data.len()
+            );
+            write_asciicast_line(
+                &mut writer,
+                &(elapsed_secs, event_code, placeholder.as_str()),
+            )?;
+        } else {
+            let chunk = String::from_utf8_lossy(&data_packet.data);
+            write_asciicast_line(&mut writer, &(elapsed_secs, event_code, chunk.as_ref()))?;
+        }
+    }
+}
+
+fn write_asciicast_line<W: Write, T: Serialize>(
+    writer: &mut W,
+    value: &T,
+) -> Result<(), PTYParserError> {
+    let line = serde_json::to_string(value).map_err(PTYParserError::Json)?;
+    writeln!(writer, "{}", line).map_err(PTYParserError::WriteError)
+}
+
 fn format_date(unix_timestamp: u64) -> String {
     let d = UNIX_EPOCH + Duration::from_secs(unix_timestamp);
     let datetime = DateTime::<Utc>::from(d);
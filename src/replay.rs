@@ -0,0 +1,77 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Read, Write},
+    thread::sleep,
+    time::Duration,
+};
+
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ReplayError {
+    #[error("could not read timing file")]
+    ReadTimings(io::Error),
+    #[error("could not read data file")]
+    ReadData(io::Error),
+    #[error("invalid timing line: {0}")]
+    InvalidTiming(String),
+    #[error("could not write to stdout")]
+    Write(io::Error),
+    #[error("replay speed must be a positive, finite number")]
+    InvalidSpeed,
+}
+
+pub struct ReplayOptions {
+    pub speed: f32,
+    pub idle_limit: Option<f32>,
+    pub instant: bool,
+}
+
+/// Replays a session recorded by [`ssh_log_cli::pty::generate_replay`] directly to stdout,
+/// without shelling out to `scriptreplay`.
+pub fn replay(
+    data_fname: &str,
+    times_fname: &str,
+    opts: &ReplayOptions,
+) -> Result<(), ReplayError> {
+    if !opts.instant && !(opts.speed.is_finite() && opts.speed > 0.0) {
+        return Err(ReplayError::InvalidSpeed);
+    }
+
+    let times_file = File::open(times_fname).map_err(ReplayError::ReadTimings)?;
+    let mut data_file = File::open(data_fname).map_err(ReplayError::ReadData)?;
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+
+    for line in BufReader::new(times_file).lines() {
+        let line = line.map_err(ReplayError::ReadTimings)?;
+        let (delay, len) = line
+            .split_once(' ')
+            .ok_or_else(|| ReplayError::InvalidTiming(line.clone()))?;
+        let delay: f32 = delay
+            .parse()
+            .map_err(|_| ReplayError::InvalidTiming(line.clone()))?;
+        let len: usize = len
+            .parse()
+            .map_err(|_| ReplayError::InvalidTiming(line.clone()))?;
+
+        if !opts.instant {
+            let mut delay = delay / opts.speed;
+            if let Some(idle_limit) = opts.idle_limit {
+                delay = delay.min(idle_limit);
+            }
+            if delay > 0.0 {
+                sleep(Duration::from_secs_f32(delay));
+            }
+        }
+
+        let mut chunk = vec![0; len];
+        data_file
+            .read_exact(&mut chunk)
+            .map_err(ReplayError::ReadData)?;
+        out.write_all(&chunk).map_err(ReplayError::Write)?;
+        out.flush().map_err(ReplayError::Write)?;
+    }
+
+    Ok(())
+}
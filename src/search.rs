@@ -0,0 +1,75 @@
+use std::io::Read;
+
+use regex::Regex;
+
+use crate::{
+    data::DataSource,
+    pty,
+    session::{SessionError, SessionReader},
+};
+
+/// A pattern to search for in decrypted session output, either a literal substring or
+/// a regular expression.
+pub enum Pattern {
+    Literal(String),
+    Regex(Regex),
+}
+
+impl Pattern {
+    fn is_match(&self, line: &str) -> bool {
+        match self {
+            Pattern::Literal(needle) => line.contains(needle.as_str()),
+            Pattern::Regex(regex) => regex.is_match(line),
+        }
+    }
+}
+
+pub struct SearchMatch {
+    pub source: DataSource,
+    pub elapsed: std::time::Duration,
+    pub line: String,
+}
+
+/// Streams a single encrypted session, reporting every line of origin/client output
+/// that matches `pattern`. Sessions are decrypted block-by-block as they are searched,
+/// the full session is never materialized in memory.
+///
+/// When `redact_client` is set, client packets recorded while the session's PTY had
+/// echo disabled (e.g. a password typed at a prompt) are skipped rather than searched
+/// or reported, so secrets typed with echo off never reach stdout. Client packets of a
+/// session with no PTY at all are skipped as well, since there is no echo state to check.
+pub fn search_session<R: Read>(
+    reader: R,
+    private_key_base64: String,
+    pattern: &Pattern,
+    redact_client: bool,
+) -> Result<Vec<SearchMatch>, SessionError> {
+    let session = SessionReader::new(reader, private_key_base64)?;
+    let echo_enabled = session
+        .metadata
+        .pty
+        .as_ref()
+        .map_or(false, pty::echo_enabled);
+
+    let mut matches = Vec::new();
+    for packet in session {
+        let packet = packet?;
+
+        if redact_client && matches!(packet.source, DataSource::Client) && !echo_enabled {
+            continue;
+        }
+
+        let text = String::from_utf8_lossy(&packet.data);
+        for line in text.lines() {
+            if pattern.is_match(line) {
+                matches.push(SearchMatch {
+                    source: packet.source,
+                    elapsed: packet.elapsed,
+                    line: line.to_string(),
+                });
+            }
+        }
+    }
+
+    Ok(matches)
+}
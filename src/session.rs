@@ -0,0 +1,75 @@
+use std::io::Read;
+
+use thiserror::Error;
+
+use crate::{
+    data::{DataDecoder, DataError, DataPacket},
+    hpke::{Ctx, HPKEDecryptionError},
+    metadata::{Metadata, MetadataError},
+};
+
+#[derive(Error, Debug)]
+pub enum SessionError {
+    #[error("could not read metadata")]
+    Metadata(#[from] MetadataError),
+    #[error("could not create decryption context")]
+    Context(#[from] HPKEDecryptionError),
+    #[error("could not decode data packet")]
+    Data(#[from] DataError),
+}
+
+/// A session whose metadata has been read but not yet decrypted, giving callers a
+/// chance to validate it (e.g. pin an expected sender) before committing to building a
+/// decryption context.
+pub struct PendingSession<R> {
+    pub metadata: Metadata,
+    reader: R,
+}
+
+impl<R: Read> PendingSession<R> {
+    pub fn read(mut reader: R) -> Result<Self, SessionError> {
+        let metadata = Metadata::read(&mut reader)?;
+        Ok(PendingSession { metadata, reader })
+    }
+
+    pub fn open(self, private_key_base64: String) -> Result<SessionReader<R>, SessionError> {
+        let ctx = Ctx::new(&self.metadata, private_key_base64, self.reader)?;
+        Ok(SessionReader {
+            metadata: self.metadata,
+            decoder: DataDecoder(ctx),
+        })
+    }
+}
+
+/// Reads and decrypts a session directly off a reader, without touching the filesystem.
+///
+/// Yields the session's [`DataPacket`]s in order via [`Iterator`], decrypting blocks
+/// lazily as they are consumed.
+pub struct SessionReader<R: Read> {
+    pub metadata: Metadata,
+    decoder: DataDecoder<Ctx<R>>,
+}
+
+impl<R: Read> SessionReader<R> {
+    pub fn new(reader: R, private_key_base64: String) -> Result<Self, SessionError> {
+        PendingSession::read(reader)?.open(private_key_base64)
+    }
+
+    /// Unwraps the underlying decoder, for callers that need to hand it to code built
+    /// directly against [`DataDecoder`] (e.g. the CLI's existing export paths).
+    pub fn into_decoder(self) -> DataDecoder<Ctx<R>> {
+        self.decoder
+    }
+}
+
+impl<R: Read> Iterator for SessionReader<R> {
+    type Item = Result<DataPacket, SessionError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.decoder.next() {
+            Ok(Some(packet)) => Some(Ok(packet)),
+            Ok(None) => None,
+            Err(e) => Some(Err(SessionError::Data(e))),
+        }
+    }
+}